@@ -1,348 +1,1062 @@
-use std::cmp;
-
-use nalgebra::DMatrix;
-
-pub struct Tree<'a> {
-    post_order: Vec<&'a TreeNode>,
-    left_most_leaf_descendant: Vec<usize>,
-    key_roots: Vec<usize>,
-}
-
-pub struct TreeNode {
-    label: String,
-    children: Vec<Box<TreeNode>>,
-}
-
-impl TreeNode {
-    pub fn new(label: &str) -> TreeNode {
-        TreeNode {
-            label: String::from(label),
-            children: vec![],
-        }
-    }
-
-    pub fn with_children(mut self, children : Vec<Box<TreeNode>>) -> TreeNode {
-        self.children = children;
-        self
-    }
-
-    fn post_order(&self) -> Vec<&TreeNode> {
-        let mut result = vec![];
-        for child in self.children.iter() {
-            result.extend(child.post_order().iter());
-        }
-        result.push(self);
-        result
-    }
-}
-
-impl<'a> Tree<'a> {
-    pub fn new(root: &'a TreeNode) -> Tree {
-        let post_order = root.post_order();
-        let left_most_leaf_descendant = Tree::left_most_leaf_descendant(&post_order);
-        let key_roots = Tree::keyroots(&post_order);
-        Tree {
-            post_order,
-            left_most_leaf_descendant,
-            key_roots,
-        }
-    }
-
-    fn left_most_leaf_descendant(post_order: &[&TreeNode]) -> Vec<usize> {
-        let mut result = Vec::with_capacity(post_order.len());
-        for (idx, node) in post_order.iter().enumerate() {
-            let left_most_child = idx - (node.post_order().len() - 1);
-            result.push(left_most_child);
-        }
-        result
-    }
-
-    fn keyroots(post_order: &[&TreeNode]) -> Vec<usize> {
-        let mut key_roots = Vec::new();
-        let mut to_look_at = Vec::new();
-        // insert root node
-        key_roots.push(post_order.len() - 1);
-        // insert appropriate child nodes
-        to_look_at.push(post_order.len() - 1);
-        while !to_look_at.is_empty() {
-            let n = to_look_at.pop().unwrap();
-            for (idx, child) in post_order[n].children.iter().enumerate() {
-                if idx > 0 {
-                    key_roots.push(Tree::id(post_order, child));
-                }
-                to_look_at.push(Tree::id(post_order, child));
-            }
-        }
-        // sort keyroots in ascending order
-        key_roots.sort();
-        key_roots
-    }
-
-    fn id(post_order: &[&TreeNode], node: &TreeNode) -> usize {
-        post_order
-            .iter()
-            .position(|&n| n as *const TreeNode == node as *const TreeNode)
-            .unwrap()
-    }
-
-    fn label_cmp(tn1: &TreeNode, tn2: &TreeNode, relabeling_cost: u64) -> u64 {
-        if tn1.label == tn2.label {
-            0u64
-        } else {
-            relabeling_cost
-        }
-    }
-
-    fn forest_distance(
-        key_root_1: usize,
-        key_root_2: usize,
-        t1: &Tree,
-        t2: &Tree,
-        td: &mut DMatrix<u64>,
-        insertion_cost: u64,
-        deletion_cost: u64,
-        relabeling_cost: u64,
-    ) {
-        let l1_i = t1.left_most_leaf_descendant[key_root_1];
-        let l2_j = t2.left_most_leaf_descendant[key_root_2];
-        let mut fd: DMatrix<u64> = DMatrix::zeros(key_root_1 - l1_i + 2, key_root_2 - l2_j + 2);
-        for i in 1..(key_root_1 - l1_i + 2) {
-            fd[(i, 0)] = fd[(i - 1, 0)] + deletion_cost;
-        }
-        for i in 1..(key_root_2 - l2_j + 2) {
-            fd[(0, i)] = fd[(0, i - 1)] + insertion_cost;
-        }
-        for i in 1..(key_root_1 - l1_i + 2) {
-            for j in 1..(key_root_2 - l2_j + 2) {
-                // check if t1 and t2 are both trees
-                if t1.left_most_leaf_descendant[i + l1_i - 1] == l1_i
-                    && t2.left_most_leaf_descendant[j + l2_j - 1] == l2_j
-                {
-                    fd[(i, j)] = cmp::min(
-                        cmp::min(
-                            fd[(i - 1, j)] + deletion_cost,
-                            fd[(i, j - 1)] + insertion_cost,
-                        ),
-                        fd[(i - 1, j - 1)]
-                            + Tree::label_cmp(
-                                t1.post_order[i + l1_i - 1],
-                                t2.post_order[j + l2_j - 1],
-                                relabeling_cost,
-                            ),
-                    );
-                    td[(i + l1_i - 1, j + l2_j - 1)] = fd[(i, j)];
-                }
-                // in this case at least t1 or t2 is a forest
-                else {
-                    fd[(i, j)] = cmp::min(
-                        cmp::min(
-                            fd[(i - 1, j)] + deletion_cost,
-                            fd[(i, j - 1)] + insertion_cost,
-                        ),
-                        fd[(
-                            t1.left_most_leaf_descendant[i + l1_i - 1] - l1_i,
-                            t2.left_most_leaf_descendant[j + l2_j - 1] - l2_j,
-                        )] + td[(i + l1_i - 1, j + l2_j - 1)],
-                    );
-                }
-            }
-        }
-    }
-
-    pub fn weighted_tree_edit_distance(
-        &self,
-        other: &Tree,
-        insertion_cost: u64,
-        deletion_cost: u64,
-        relabeling_cost: u64,
-    ) -> u64 {
-        let mut td: DMatrix<u64> = DMatrix::zeros(self.post_order.len(), other.post_order.len());
-        for &x in self.key_roots.iter() {
-            for &y in other.key_roots.iter() {
-                Tree::forest_distance(
-                    x,
-                    y,
-                    self,
-                    other,
-                    &mut td,
-                    insertion_cost,
-                    deletion_cost,
-                    relabeling_cost,
-                );
-            }
-        }
-        td[(self.post_order.len() - 1, other.post_order.len() - 1)]
-    }
-
-    pub fn tree_edit_distance(&self, other: &Tree) -> u64 {
-        self.weighted_tree_edit_distance(other, 1, 1, 1)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // macro for more convenient creation of trees
-    macro_rules! tree {
-        ($r:expr) => {
-            TreeNode::new($r)
-        };
-        ($r:expr,[ $( $c:expr ),* ] )=> {{
-            let mut root = TreeNode::new($r);
-            $(
-                root.children.push(Box::new($c));
-            )*
-            root
-        }};
-    }
-
-    #[test]
-    fn test_post_order() {
-        // example tree given by root_node with node number in post order depicted next to label
-        //                             A  5
-        //                             +
-        //                             |
-        //                       +-----+-----+
-        //                       |     |     |
-        //                       v     v     v
-        //                       B 0   C 3   D 4
-        //                             +
-        //                             |
-        //                          +--+--+
-        //                          |     |
-        //                          v     v
-        //                          E 1   F 2
-
-        let root_node = tree!(
-            "A",
-            [tree!("B"), tree!("C", [tree!("E"), tree!("F")]), tree!("D")]
-        );
-        assert_eq!("B", root_node.post_order()[0].label);
-        assert_eq!("E", root_node.post_order()[1].label);
-        assert_eq!("F", root_node.post_order()[2].label);
-        assert_eq!("C", root_node.post_order()[3].label);
-        assert_eq!("D", root_node.post_order()[4].label);
-        assert_eq!("A", root_node.post_order()[5].label);
-    }
-
-    #[test]
-    fn test_leftmost_leaf_descendant() {
-        // example tree given by root_node with node number in post order depicted next to label
-        //                             A  5
-        //                             +
-        //                             |
-        //                       +-----+-----+
-        //                       |     |     |
-        //                       v     v     v
-        //                       B 0   C 3   D 4
-        //                             +
-        //                             |
-        //                          +--+--+
-        //                          |     |
-        //                          v     v
-        //                          E 1   F 2
-
-        let root_node = tree!(
-            "A",
-            [tree!("B"), tree!("C", [tree!("E"), tree!("F")]), tree!("D")]
-        );
-        let tree = Tree::new(&root_node);
-
-        assert_eq!(0, tree.left_most_leaf_descendant[5]);
-        assert_eq!(4, tree.left_most_leaf_descendant[4]);
-        assert_eq!(1, tree.left_most_leaf_descendant[3]);
-        assert_eq!(2, tree.left_most_leaf_descendant[2]);
-        assert_eq!(1, tree.left_most_leaf_descendant[1]);
-        assert_eq!(0, tree.left_most_leaf_descendant[0]);
-    }
-
-    #[test]
-    fn test_key_roots() {
-        // example tree given by root_node with node number in post order depicted next to label
-        //                             A  5
-        //                             +
-        //                             |
-        //                       +-----+-----+
-        //                       |     |     |
-        //                       v     v     v
-        //                       B 0   C 3   D 4
-        //                             +
-        //                             |
-        //                          +--+--+
-        //                          |     |
-        //                          v     v
-        //                          E 1   F 2
-
-        let root_node = tree!(
-            "A",
-            [tree!("B"), tree!("C", [tree!("E"), tree!("F")]), tree!("D")]
-        );
-        let tree = Tree::new(&root_node);
-
-        assert_eq!(2, tree.key_roots[0]);
-        assert_eq!(3, tree.key_roots[1]);
-        assert_eq!(4, tree.key_roots[2]);
-        assert_eq!(5, tree.key_roots[3]);
-    }
-
-    #[test]
-    fn test_self_distance_is_zero() {
-        let tree_1_root_node = tree!(
-            "A",
-            [
-                tree!("B"),
-                tree!("C", [tree!("C1"), tree!("C2")]),
-                tree!("D")
-            ]
-        );
-        let tree_2_root_node = tree!("X");
-
-        let tree_1 = Tree::new(&tree_1_root_node);
-        let tree_2 = Tree::new(&tree_2_root_node);
-
-        // distance between a tree and itself should always be zero
-        assert_eq!(0, tree_1.tree_edit_distance(&tree_1));
-        assert_eq!(0, tree_2.tree_edit_distance(&tree_2));
-
-        // distance to any tree that is different must not be zero
-        assert_ne!(0, tree_1.tree_edit_distance(&tree_2));
-        assert_ne!(0, tree_2.tree_edit_distance(&tree_1));
-    }
-
-    #[test]
-    fn test_distance_with_single_node_trees() {
-        let tree_1_root_node = tree!("A");
-        let tree_2_root_node = tree!("B");
-
-        let tree_1 = Tree::new(&tree_1_root_node);
-        let tree_2 = Tree::new(&tree_2_root_node);
-
-        assert_eq!(1, tree_1.tree_edit_distance(&tree_2));
-        assert_eq!(1, tree_2.tree_edit_distance(&tree_1));
-    }
-
-    #[test]
-    fn test_distance_with_trees() {
-        let tree_1_root_node = tree!("A", [tree!("B"), tree!("C"), tree!("D", [tree!("E")])]);
-        let tree_2_root_node = tree!("X", [tree!("C"), tree!("Y", [tree!("Z")])]);
-
-        let tree_1 = Tree::new(&tree_1_root_node);
-        let tree_2 = Tree::new(&tree_2_root_node);
-
-        assert_eq!(4, tree_1.tree_edit_distance(&tree_2));
-        assert_eq!(4, tree_2.tree_edit_distance(&tree_1));
-    }
-
-    #[test]
-    fn test_weighted_distance() {
-        let tree_1_root_node = tree!("A");
-        let tree_2_root_node = tree!("B");
-
-        let tree_1 = Tree::new(&tree_1_root_node);
-        let tree_2 = Tree::new(&tree_2_root_node);
-
-        assert_eq!(2, tree_1.weighted_tree_edit_distance(&tree_2, 1, 1, 3));
-        assert_eq!(2, tree_2.weighted_tree_edit_distance(&tree_1, 1, 1, 3));
-    }
-}
+use std::cmp;
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+pub struct Tree<'a, L> {
+    post_order: Vec<&'a TreeNode<L>>,
+    left_most_leaf_descendant: Vec<usize>,
+    key_roots: Vec<usize>,
+}
+
+/// A single step of an optimal edit script turning one tree into another.
+///
+/// Nodes are identified by their post-order index in the tree they belong to:
+/// `Delete` refers to a node of the source tree, `Insert` to a node of the
+/// target tree, and `Relabel`/`Match` relate a source node (`from`) to a target
+/// node (`to`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditOp {
+    Delete(usize),
+    Insert(usize),
+    Relabel { from: usize, to: usize },
+    Match { from: usize, to: usize },
+}
+
+#[derive(Debug)]
+pub struct TreeNode<L> {
+    label: L,
+    children: Vec<Box<TreeNode<L>>>,
+}
+
+impl<L> TreeNode<L> {
+    pub fn new(label: L) -> TreeNode<L> {
+        TreeNode {
+            label,
+            children: vec![],
+        }
+    }
+
+    pub fn with_children(mut self, children: Vec<Box<TreeNode<L>>>) -> TreeNode<L> {
+        self.children = children;
+        self
+    }
+
+    #[cfg(test)]
+    fn post_order(&self) -> Vec<&TreeNode<L>> {
+        let mut result = vec![];
+        for child in self.children.iter() {
+            result.extend(child.post_order().iter());
+        }
+        result.push(self);
+        result
+    }
+}
+
+/// What went wrong while parsing a bracketed tree, and where.
+///
+/// `offset` is the byte offset into the input at which the error was detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A character appeared where a `,` or `)` was expected.
+    UnexpectedChar(char),
+    /// The input ended in the middle of a node or an escape sequence.
+    UnexpectedEnd,
+    /// The root node was fully parsed but input remained.
+    TrailingInput,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.kind {
+            ParseErrorKind::UnexpectedChar(c) => {
+                write!(f, "unexpected character '{}' at byte {}", c, self.offset)
+            }
+            ParseErrorKind::UnexpectedEnd => {
+                write!(f, "unexpected end of input at byte {}", self.offset)
+            }
+            ParseErrorKind::TrailingInput => {
+                write!(f, "trailing input after root node at byte {}", self.offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl TreeNode<String> {
+    /// Parses a tree from its bracketed textual form, e.g. `"A(B,C(E,F),D)"`.
+    ///
+    /// A node is a label optionally followed by a parenthesized, comma
+    /// separated list of child nodes. The characters `(`, `)`, `,` and `\`
+    /// may appear literally inside a label when escaped with a backslash. The
+    /// byte offset of the first syntax error is reported in the [`ParseError`].
+    pub fn from_bracket(input: &str) -> Result<TreeNode<String>, ParseError> {
+        let mut parser = BracketParser { input, pos: 0 };
+        let node = parser.node()?;
+        if parser.pos != input.len() {
+            return Err(ParseError {
+                offset: parser.pos,
+                kind: ParseErrorKind::TrailingInput,
+            });
+        }
+        Ok(node)
+    }
+
+    /// Serializes the tree into the bracketed form accepted by
+    /// [`from_bracket`](TreeNode::from_bracket), escaping any `(`, `)`, `,` or
+    /// `\` occurring in a label so the result round-trips.
+    pub fn to_bracket(&self) -> String {
+        let mut out = String::new();
+        self.write_bracket(&mut out);
+        out
+    }
+
+    fn write_bracket(&self, out: &mut String) {
+        for c in self.label.chars() {
+            if matches!(c, '(' | ')' | ',' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        if !self.children.is_empty() {
+            out.push('(');
+            for (idx, child) in self.children.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                child.write_bracket(out);
+            }
+            out.push(')');
+        }
+    }
+}
+
+/// Recursive-descent parser backing [`TreeNode::from_bracket`].
+struct BracketParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl BracketParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn label(&mut self) -> Result<String, ParseError> {
+        let mut label = String::new();
+        loop {
+            match self.peek() {
+                None | Some('(') | Some(')') | Some(',') => break,
+                Some('\\') => {
+                    let escape_offset = self.pos;
+                    self.bump();
+                    match self.bump() {
+                        Some(c) => label.push(c),
+                        None => {
+                            return Err(ParseError {
+                                offset: escape_offset,
+                                kind: ParseErrorKind::UnexpectedEnd,
+                            })
+                        }
+                    }
+                }
+                Some(c) => {
+                    label.push(c);
+                    self.bump();
+                }
+            }
+        }
+        Ok(label)
+    }
+
+    fn node(&mut self) -> Result<TreeNode<String>, ParseError> {
+        let mut node = TreeNode::new(self.label()?);
+        if self.peek() == Some('(') {
+            self.bump();
+            let mut children = Vec::new();
+            loop {
+                children.push(Box::new(self.node()?));
+                match self.peek() {
+                    Some(',') => {
+                        self.bump();
+                    }
+                    Some(')') => {
+                        self.bump();
+                        break;
+                    }
+                    None => {
+                        return Err(ParseError {
+                            offset: self.pos,
+                            kind: ParseErrorKind::UnexpectedEnd,
+                        })
+                    }
+                    Some(c) => {
+                        return Err(ParseError {
+                            offset: self.pos,
+                            kind: ParseErrorKind::UnexpectedChar(c),
+                        })
+                    }
+                }
+            }
+            node = node.with_children(children);
+        }
+        Ok(node)
+    }
+}
+
+/// A frame of the explicit post-order walk used to build a [`Tree`].
+struct Frame<'a, L> {
+    node: &'a TreeNode<L>,
+    next_child: usize,
+    first_child: Option<usize>,
+}
+
+impl<'a, L> Tree<'a, L> {
+    pub fn new(root: &'a TreeNode<L>) -> Tree<'a, L> {
+        let mut post_order: Vec<&TreeNode<L>> = Vec::new();
+        let mut left_most_leaf_descendant: Vec<usize> = Vec::new();
+
+        // Single iterative post-order walk: every node is assigned its
+        // post-order index exactly once, and its left-most leaf descendant is
+        // derived from the children it has already emitted. Construction is
+        // linear and never recurses (deep or degenerate trees cannot overflow
+        // the stack).
+        let mut stack = vec![Frame {
+            node: root,
+            next_child: 0,
+            first_child: None,
+        }];
+        while let Some(top) = stack.last_mut() {
+            if top.next_child < top.node.children.len() {
+                let node = top.node;
+                let child_idx = top.next_child;
+                top.next_child += 1;
+                stack.push(Frame {
+                    node: &node.children[child_idx],
+                    next_child: 0,
+                    first_child: None,
+                });
+            } else {
+                let frame = stack.pop().unwrap();
+                let index = post_order.len();
+                let left_most = match frame.first_child {
+                    Some(first) => left_most_leaf_descendant[first],
+                    None => index,
+                };
+                post_order.push(frame.node);
+                left_most_leaf_descendant.push(left_most);
+                if let Some(parent) = stack.last_mut() {
+                    if parent.first_child.is_none() {
+                        parent.first_child = Some(index);
+                    }
+                }
+            }
+        }
+
+        let key_roots = Self::keyroots(&left_most_leaf_descendant);
+        Tree {
+            post_order,
+            left_most_leaf_descendant,
+            key_roots,
+        }
+    }
+
+    fn keyroots(left_most_leaf_descendant: &[usize]) -> Vec<usize> {
+        // A node is a key root when it is the highest-indexed node reaching its
+        // left-most leaf; walking from the root down, the first node seen for
+        // each distinct left-most leaf is exactly that node.
+        let n = left_most_leaf_descendant.len();
+        let mut seen = vec![false; n];
+        let mut key_roots = Vec::new();
+        for i in (0..n).rev() {
+            let leaf = left_most_leaf_descendant[i];
+            if !seen[leaf] {
+                seen[leaf] = true;
+                key_roots.push(i);
+            }
+        }
+        // sort keyroots in ascending order
+        key_roots.sort();
+        key_roots
+    }
+
+    fn label_cmp<FR>(tn1: &TreeNode<L>, tn2: &TreeNode<L>, rel_cost: &FR) -> u64
+    where
+        FR: Fn(&L, &L) -> u64,
+    {
+        rel_cost(&tn1.label, &tn2.label)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn forest_distance<FI, FD, FR>(
+        key_root_1: usize,
+        key_root_2: usize,
+        t1: &Tree<L>,
+        t2: &Tree<L>,
+        td: &mut DMatrix<u64>,
+        ins_cost: &FI,
+        del_cost: &FD,
+        rel_cost: &FR,
+    ) -> DMatrix<u64>
+    where
+        FI: Fn(&L) -> u64,
+        FD: Fn(&L) -> u64,
+        FR: Fn(&L, &L) -> u64,
+    {
+        let l1_i = t1.left_most_leaf_descendant[key_root_1];
+        let l2_j = t2.left_most_leaf_descendant[key_root_2];
+        let mut fd: DMatrix<u64> = DMatrix::zeros(key_root_1 - l1_i + 2, key_root_2 - l2_j + 2);
+        for i in 1..(key_root_1 - l1_i + 2) {
+            fd[(i, 0)] = fd[(i - 1, 0)] + del_cost(&t1.post_order[i + l1_i - 1].label);
+        }
+        for i in 1..(key_root_2 - l2_j + 2) {
+            fd[(0, i)] = fd[(0, i - 1)] + ins_cost(&t2.post_order[i + l2_j - 1].label);
+        }
+        for i in 1..(key_root_1 - l1_i + 2) {
+            for j in 1..(key_root_2 - l2_j + 2) {
+                // check if t1 and t2 are both trees
+                if t1.left_most_leaf_descendant[i + l1_i - 1] == l1_i
+                    && t2.left_most_leaf_descendant[j + l2_j - 1] == l2_j
+                {
+                    fd[(i, j)] = cmp::min(
+                        cmp::min(
+                            fd[(i - 1, j)] + del_cost(&t1.post_order[i + l1_i - 1].label),
+                            fd[(i, j - 1)] + ins_cost(&t2.post_order[j + l2_j - 1].label),
+                        ),
+                        fd[(i - 1, j - 1)]
+                            + Tree::label_cmp(
+                                t1.post_order[i + l1_i - 1],
+                                t2.post_order[j + l2_j - 1],
+                                rel_cost,
+                            ),
+                    );
+                    td[(i + l1_i - 1, j + l2_j - 1)] = fd[(i, j)];
+                }
+                // in this case at least t1 or t2 is a forest
+                else {
+                    fd[(i, j)] = cmp::min(
+                        cmp::min(
+                            fd[(i - 1, j)] + del_cost(&t1.post_order[i + l1_i - 1].label),
+                            fd[(i, j - 1)] + ins_cost(&t2.post_order[j + l2_j - 1].label),
+                        ),
+                        fd[(
+                            t1.left_most_leaf_descendant[i + l1_i - 1] - l1_i,
+                            t2.left_most_leaf_descendant[j + l2_j - 1] - l2_j,
+                        )] + td[(i + l1_i - 1, j + l2_j - 1)],
+                    );
+                }
+            }
+        }
+        fd
+    }
+
+    /// Min-cost ordered alignment of two child-subtree sequences under the
+    /// structure-preserving constraint: every subtree maps wholly to a subtree.
+    ///
+    /// Substituting `a[p]` for `b[q]` costs the already-computed subtree
+    /// distance `td[(a[p], b[q])]`, while an unmatched subtree is deleted or
+    /// inserted in full (`tree_delete`/`tree_insert`). This is the matched case
+    /// of [`constrained_tree_edit_distance_with`](Tree::constrained_tree_edit_distance_with).
+    fn constrained_forest_distance(
+        a: &[usize],
+        b: &[usize],
+        td: &DMatrix<u64>,
+        tree_delete: &[u64],
+        tree_insert: &[u64],
+    ) -> u64 {
+        let mut fd: DMatrix<u64> = DMatrix::zeros(a.len() + 1, b.len() + 1);
+        for p in 1..=a.len() {
+            fd[(p, 0)] = fd[(p - 1, 0)] + tree_delete[a[p - 1]];
+        }
+        for q in 1..=b.len() {
+            fd[(0, q)] = fd[(0, q - 1)] + tree_insert[b[q - 1]];
+        }
+        for p in 1..=a.len() {
+            for q in 1..=b.len() {
+                fd[(p, q)] = cmp::min(
+                    cmp::min(
+                        fd[(p - 1, q)] + tree_delete[a[p - 1]],
+                        fd[(p, q - 1)] + tree_insert[b[q - 1]],
+                    ),
+                    fd[(p - 1, q - 1)] + td[(a[p - 1], b[q - 1])],
+                );
+            }
+        }
+        fd[(a.len(), b.len())]
+    }
+
+    /// Weighted tree edit distance with user-supplied cost functions.
+    ///
+    /// `ins_cost`/`del_cost` weigh inserting and deleting a node by its own
+    /// label, and `rel_cost` weighs turning one label into another (returning 0
+    /// when they should be considered equal). This computes a true weighted
+    /// edit distance; the constant-weight `weighted_tree_edit_distance` is a
+    /// thin wrapper around it.
+    pub fn weighted_tree_edit_distance_with<FI, FD, FR>(
+        &self,
+        other: &Tree<L>,
+        ins_cost: FI,
+        del_cost: FD,
+        rel_cost: FR,
+    ) -> u64
+    where
+        FI: Fn(&L) -> u64,
+        FD: Fn(&L) -> u64,
+        FR: Fn(&L, &L) -> u64,
+    {
+        let mut td: DMatrix<u64> = DMatrix::zeros(self.post_order.len(), other.post_order.len());
+        for &x in self.key_roots.iter() {
+            for &y in other.key_roots.iter() {
+                Tree::forest_distance(x, y, self, other, &mut td, &ins_cost, &del_cost, &rel_cost);
+            }
+        }
+        td[(self.post_order.len() - 1, other.post_order.len() - 1)]
+    }
+
+    /// The original post-order index of each node's children, left-to-right,
+    /// recovered from the left-most leaf descendants without any identity
+    /// lookups.
+    fn child_indices(&self) -> Vec<Vec<usize>> {
+        let n = self.post_order.len();
+        let mut children = vec![Vec::new(); n];
+        for (i, &lmld_i) in self.left_most_leaf_descendant.iter().enumerate() {
+            // a leaf is its own left-most leaf descendant
+            if lmld_i == i {
+                continue;
+            }
+            let mut child = i - 1;
+            loop {
+                children[i].push(child);
+                if self.left_most_leaf_descendant[child] == lmld_i {
+                    break;
+                }
+                child = self.left_most_leaf_descendant[child] - 1;
+            }
+            children[i].reverse();
+        }
+        children
+    }
+
+    pub fn weighted_tree_edit_distance(
+        &self,
+        other: &Tree<L>,
+        insertion_cost: u64,
+        deletion_cost: u64,
+        relabeling_cost: u64,
+    ) -> u64
+    where
+        L: PartialEq,
+    {
+        self.weighted_tree_edit_distance_with(
+            other,
+            move |_| insertion_cost,
+            move |_| deletion_cost,
+            move |a, b| if a == b { 0 } else { relabeling_cost },
+        )
+    }
+
+    pub fn tree_edit_distance(&self, other: &Tree<L>) -> u64
+    where
+        L: PartialEq,
+    {
+        self.weighted_tree_edit_distance(other, 1, 1, 1)
+    }
+
+    /// Zhang's *constrained* (structure-preserving) tree edit distance.
+    ///
+    /// Unlike the general distance, the induced mapping must send two disjoint
+    /// subtrees of one tree to two disjoint subtrees of the other, so related
+    /// nodes never cross into a common ancestor. This is usually the variant
+    /// wanted for comparing structured documents; it is always at least the
+    /// general distance and remains polynomial. The constant-weight entry point
+    /// delegates to [`constrained_tree_edit_distance_with`](Tree::constrained_tree_edit_distance_with).
+    pub fn constrained_tree_edit_distance(
+        &self,
+        other: &Tree<L>,
+        insertion_cost: u64,
+        deletion_cost: u64,
+        relabeling_cost: u64,
+    ) -> u64
+    where
+        L: PartialEq,
+    {
+        self.constrained_tree_edit_distance_with(
+            other,
+            move |_| insertion_cost,
+            move |_| deletion_cost,
+            move |a, b| if a == b { 0 } else { relabeling_cost },
+        )
+    }
+
+    /// [`constrained_tree_edit_distance`](Tree::constrained_tree_edit_distance)
+    /// with user-supplied cost functions.
+    ///
+    /// Subtree distances are filled in post-order so that `td[(i, j)]` is the
+    /// constrained distance between the subtrees rooted at `i` and `j`. Each
+    /// cell is the cheapest of deleting `i`'s root (mapping the whole other
+    /// subtree into one of its children), inserting `j`'s root, or relabeling
+    /// `i` into `j` and aligning their child sequences with
+    /// [`constrained_forest_distance`](Tree::constrained_forest_distance).
+    pub fn constrained_tree_edit_distance_with<FI, FD, FR>(
+        &self,
+        other: &Tree<L>,
+        ins_cost: FI,
+        del_cost: FD,
+        rel_cost: FR,
+    ) -> u64
+    where
+        FI: Fn(&L) -> u64,
+        FD: Fn(&L) -> u64,
+        FR: Fn(&L, &L) -> u64,
+    {
+        let n1 = self.post_order.len();
+        let n2 = other.post_order.len();
+        let children_1 = self.child_indices();
+        let children_2 = other.child_indices();
+
+        // cost of deleting / inserting each subtree whole, accumulated bottom-up
+        let mut tree_delete = vec![0u64; n1];
+        for i in 0..n1 {
+            let mut cost = del_cost(&self.post_order[i].label);
+            for &child in &children_1[i] {
+                cost += tree_delete[child];
+            }
+            tree_delete[i] = cost;
+        }
+        let mut tree_insert = vec![0u64; n2];
+        for j in 0..n2 {
+            let mut cost = ins_cost(&other.post_order[j].label);
+            for &child in &children_2[j] {
+                cost += tree_insert[child];
+            }
+            tree_insert[j] = cost;
+        }
+
+        let mut td: DMatrix<u64> = DMatrix::zeros(n1, n2);
+        for i in 0..n1 {
+            let del_i = del_cost(&self.post_order[i].label);
+            // total cost of deleting all of i's child subtrees
+            let children_delete = tree_delete[i] - del_i;
+            for j in 0..n2 {
+                let ins_j = ins_cost(&other.post_order[j].label);
+                let children_insert = tree_insert[j] - ins_j;
+
+                let matched = Self::constrained_forest_distance(
+                    &children_1[i],
+                    &children_2[j],
+                    &td,
+                    &tree_delete,
+                    &tree_insert,
+                ) + Tree::label_cmp(self.post_order[i], other.post_order[j], &rel_cost);
+
+                // delete i's root; the other subtree maps into its best child
+                let deleted = del_i
+                    + if children_1[i].is_empty() {
+                        children_delete + tree_insert[j]
+                    } else {
+                        children_1[i]
+                            .iter()
+                            .map(|&s| td[(s, j)] + (children_delete - tree_delete[s]))
+                            .min()
+                            .unwrap()
+                    };
+
+                // insert j's root; this subtree maps into j's best child
+                let inserted = ins_j
+                    + if children_2[j].is_empty() {
+                        children_insert + tree_delete[i]
+                    } else {
+                        children_2[j]
+                            .iter()
+                            .map(|&t| td[(i, t)] + (children_insert - tree_insert[t]))
+                            .min()
+                            .unwrap()
+                    };
+
+                td[(i, j)] = cmp::min(cmp::min(matched, deleted), inserted);
+            }
+        }
+        td[(n1 - 1, n2 - 1)]
+    }
+
+    /// Computes an optimal edit script turning `self` into `other`.
+    ///
+    /// The returned operations have the same total cost as
+    /// `weighted_tree_edit_distance` with the same weights. The per-keyroot
+    /// forest-distance matrices are retained so the minimizing transition at
+    /// each cell can be replayed; ties are broken with a fixed priority
+    /// (deletion, then insertion, then relabel/match) so the script is
+    /// deterministic.
+    pub fn edit_script(
+        &self,
+        other: &Tree<L>,
+        insertion_cost: u64,
+        deletion_cost: u64,
+        relabeling_cost: u64,
+    ) -> Vec<EditOp>
+    where
+        L: PartialEq,
+    {
+        self.edit_script_with(
+            other,
+            move |_| insertion_cost,
+            move |_| deletion_cost,
+            move |a, b| if a == b { 0 } else { relabeling_cost },
+        )
+    }
+
+    /// Like `edit_script`, but with the user-supplied cost functions of
+    /// `weighted_tree_edit_distance_with`. A transition is emitted as `Match`
+    /// when its relabel cost is zero and as `Relabel` otherwise.
+    pub fn edit_script_with<FI, FD, FR>(
+        &self,
+        other: &Tree<L>,
+        ins_cost: FI,
+        del_cost: FD,
+        rel_cost: FR,
+    ) -> Vec<EditOp>
+    where
+        FI: Fn(&L) -> u64,
+        FD: Fn(&L) -> u64,
+        FR: Fn(&L, &L) -> u64,
+    {
+        let mut td: DMatrix<u64> = DMatrix::zeros(self.post_order.len(), other.post_order.len());
+        let mut forest_distances: HashMap<(usize, usize), DMatrix<u64>> = HashMap::new();
+        for &x in self.key_roots.iter() {
+            for &y in other.key_roots.iter() {
+                let fd =
+                    Tree::forest_distance(x, y, self, other, &mut td, &ins_cost, &del_cost, &rel_cost);
+                forest_distances.insert((x, y), fd);
+            }
+        }
+        let mut script = Vec::new();
+        self.backtrace(
+            other,
+            self.post_order.len() - 1,
+            other.post_order.len() - 1,
+            &forest_distances,
+            &ins_cost,
+            &del_cost,
+            &rel_cost,
+            &mut script,
+        );
+        script.reverse();
+        script
+    }
+
+    /// The key root owning `node`'s subtree: the unique key root sharing its
+    /// left-most leaf descendant, and hence the one whose forest-distance
+    /// matrix holds the cells for that subtree.
+    fn owning_key_root(&self, node: usize) -> usize {
+        let lmld = self.left_most_leaf_descendant[node];
+        *self
+            .key_roots
+            .iter()
+            .find(|&&k| self.left_most_leaf_descendant[k] == lmld)
+            .unwrap()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn backtrace<FI, FD, FR>(
+        &self,
+        other: &Tree<L>,
+        node_1: usize,
+        node_2: usize,
+        forest_distances: &HashMap<(usize, usize), DMatrix<u64>>,
+        ins_cost: &FI,
+        del_cost: &FD,
+        rel_cost: &FR,
+        script: &mut Vec<EditOp>,
+    ) where
+        FI: Fn(&L) -> u64,
+        FD: Fn(&L) -> u64,
+        FR: Fn(&L, &L) -> u64,
+    {
+        // The matrix is keyed by key-root pairs, but a matched subtree root need
+        // not itself be a key root; its cells live in the matrix of the key root
+        // that owns it. Both share the subtree's left-most leaf, so indexing is
+        // unchanged.
+        let fd = &forest_distances[&(self.owning_key_root(node_1), other.owning_key_root(node_2))];
+        let l1 = self.left_most_leaf_descendant[node_1];
+        let l2 = other.left_most_leaf_descendant[node_2];
+        let mut i = node_1 - l1 + 1;
+        let mut j = node_2 - l2 + 1;
+        while i > 0 || j > 0 {
+            if i > 0 && fd[(i, j)] == fd[(i - 1, j)] + del_cost(&self.post_order[l1 + i - 1].label) {
+                script.push(EditOp::Delete(l1 + i - 1));
+                i -= 1;
+            } else if j > 0
+                && fd[(i, j)] == fd[(i, j - 1)] + ins_cost(&other.post_order[l2 + j - 1].label)
+            {
+                script.push(EditOp::Insert(l2 + j - 1));
+                j -= 1;
+            } else {
+                let node_1 = l1 + i - 1;
+                let node_2 = l2 + j - 1;
+                // both subforests end in a whole tree: the roots are matched or relabeled
+                if self.left_most_leaf_descendant[node_1] == l1
+                    && other.left_most_leaf_descendant[node_2] == l2
+                {
+                    if Tree::label_cmp(self.post_order[node_1], other.post_order[node_2], rel_cost)
+                        == 0
+                    {
+                        script.push(EditOp::Match {
+                            from: node_1,
+                            to: node_2,
+                        });
+                    } else {
+                        script.push(EditOp::Relabel {
+                            from: node_1,
+                            to: node_2,
+                        });
+                    }
+                    i -= 1;
+                    j -= 1;
+                }
+                // otherwise the trailing trees were matched as a whole: descend
+                // into their own keyroot matrix and carry on with what is left
+                else {
+                    self.backtrace(
+                        other,
+                        node_1,
+                        node_2,
+                        forest_distances,
+                        ins_cost,
+                        del_cost,
+                        rel_cost,
+                        script,
+                    );
+                    i = self.left_most_leaf_descendant[node_1] - l1;
+                    j = other.left_most_leaf_descendant[node_2] - l2;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // macro for more convenient creation of trees
+    macro_rules! tree {
+        ($r:expr) => {
+            TreeNode::new($r)
+        };
+        ($r:expr,[ $( $c:expr ),* ] )=> {{
+            let mut root = TreeNode::new($r);
+            $(
+                root.children.push(Box::new($c));
+            )*
+            root
+        }};
+    }
+
+    #[test]
+    fn test_post_order() {
+        // example tree given by root_node with node number in post order depicted next to label
+        //                             A  5
+        //                             +
+        //                             |
+        //                       +-----+-----+
+        //                       |     |     |
+        //                       v     v     v
+        //                       B 0   C 3   D 4
+        //                             +
+        //                             |
+        //                          +--+--+
+        //                          |     |
+        //                          v     v
+        //                          E 1   F 2
+
+        let root_node = tree!(
+            "A",
+            [tree!("B"), tree!("C", [tree!("E"), tree!("F")]), tree!("D")]
+        );
+        assert_eq!("B", root_node.post_order()[0].label);
+        assert_eq!("E", root_node.post_order()[1].label);
+        assert_eq!("F", root_node.post_order()[2].label);
+        assert_eq!("C", root_node.post_order()[3].label);
+        assert_eq!("D", root_node.post_order()[4].label);
+        assert_eq!("A", root_node.post_order()[5].label);
+    }
+
+    #[test]
+    fn test_leftmost_leaf_descendant() {
+        // example tree given by root_node with node number in post order depicted next to label
+        //                             A  5
+        //                             +
+        //                             |
+        //                       +-----+-----+
+        //                       |     |     |
+        //                       v     v     v
+        //                       B 0   C 3   D 4
+        //                             +
+        //                             |
+        //                          +--+--+
+        //                          |     |
+        //                          v     v
+        //                          E 1   F 2
+
+        let root_node = tree!(
+            "A",
+            [tree!("B"), tree!("C", [tree!("E"), tree!("F")]), tree!("D")]
+        );
+        let tree = Tree::new(&root_node);
+
+        assert_eq!(0, tree.left_most_leaf_descendant[5]);
+        assert_eq!(4, tree.left_most_leaf_descendant[4]);
+        assert_eq!(1, tree.left_most_leaf_descendant[3]);
+        assert_eq!(2, tree.left_most_leaf_descendant[2]);
+        assert_eq!(1, tree.left_most_leaf_descendant[1]);
+        assert_eq!(0, tree.left_most_leaf_descendant[0]);
+    }
+
+    #[test]
+    fn test_key_roots() {
+        // example tree given by root_node with node number in post order depicted next to label
+        //                             A  5
+        //                             +
+        //                             |
+        //                       +-----+-----+
+        //                       |     |     |
+        //                       v     v     v
+        //                       B 0   C 3   D 4
+        //                             +
+        //                             |
+        //                          +--+--+
+        //                          |     |
+        //                          v     v
+        //                          E 1   F 2
+
+        let root_node = tree!(
+            "A",
+            [tree!("B"), tree!("C", [tree!("E"), tree!("F")]), tree!("D")]
+        );
+        let tree = Tree::new(&root_node);
+
+        assert_eq!(2, tree.key_roots[0]);
+        assert_eq!(3, tree.key_roots[1]);
+        assert_eq!(4, tree.key_roots[2]);
+        assert_eq!(5, tree.key_roots[3]);
+    }
+
+    #[test]
+    fn test_self_distance_is_zero() {
+        let tree_1_root_node = tree!(
+            "A",
+            [
+                tree!("B"),
+                tree!("C", [tree!("C1"), tree!("C2")]),
+                tree!("D")
+            ]
+        );
+        let tree_2_root_node = tree!("X");
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        // distance between a tree and itself should always be zero
+        assert_eq!(0, tree_1.tree_edit_distance(&tree_1));
+        assert_eq!(0, tree_2.tree_edit_distance(&tree_2));
+
+        // distance to any tree that is different must not be zero
+        assert_ne!(0, tree_1.tree_edit_distance(&tree_2));
+        assert_ne!(0, tree_2.tree_edit_distance(&tree_1));
+    }
+
+    #[test]
+    fn test_distance_with_single_node_trees() {
+        let tree_1_root_node = tree!("A");
+        let tree_2_root_node = tree!("B");
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        assert_eq!(1, tree_1.tree_edit_distance(&tree_2));
+        assert_eq!(1, tree_2.tree_edit_distance(&tree_1));
+    }
+
+    #[test]
+    fn test_distance_with_trees() {
+        let tree_1_root_node = tree!("A", [tree!("B"), tree!("C"), tree!("D", [tree!("E")])]);
+        let tree_2_root_node = tree!("X", [tree!("C"), tree!("Y", [tree!("Z")])]);
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        assert_eq!(4, tree_1.tree_edit_distance(&tree_2));
+        assert_eq!(4, tree_2.tree_edit_distance(&tree_1));
+    }
+
+    #[test]
+    fn test_weighted_distance() {
+        let tree_1_root_node = tree!("A");
+        let tree_2_root_node = tree!("B");
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        assert_eq!(2, tree_1.weighted_tree_edit_distance(&tree_2, 1, 1, 3));
+        assert_eq!(2, tree_2.weighted_tree_edit_distance(&tree_1, 1, 1, 3));
+    }
+
+    #[test]
+    fn test_edit_script_matches_distance() {
+        let tree_1_root_node = tree!("A", [tree!("B"), tree!("C")]);
+        let tree_2_root_node = tree!("A", [tree!("B"), tree!("D")]);
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        let script = tree_1.edit_script(&tree_2, 1, 1, 1);
+
+        // the script relabels C into D and matches everything else
+        assert!(script.contains(&EditOp::Relabel { from: 1, to: 1 }));
+
+        // replaying the script must cost exactly the edit distance
+        let cost: u64 = script
+            .iter()
+            .map(|op| match op {
+                EditOp::Delete(_) | EditOp::Insert(_) | EditOp::Relabel { .. } => 1,
+                EditOp::Match { .. } => 0,
+            })
+            .sum();
+        assert_eq!(cost, tree_1.tree_edit_distance(&tree_2));
+    }
+
+    #[test]
+    fn test_edit_script_descends_into_matched_subtree() {
+        // the matched subtree C (the first child on one side, the second on the
+        // other) forces the backtrace into a forest matrix whose owning key
+        // root differs from the subtree root it starts from; looking it up by
+        // the raw node index used to panic
+        let tree_1_root_node = tree!("A", [tree!("C")]);
+        let tree_2_root_node = tree!("A", [tree!("B"), tree!("C")]);
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        let script = tree_1.edit_script(&tree_2, 1, 1, 1);
+
+        let cost: u64 = script
+            .iter()
+            .map(|op| match op {
+                EditOp::Delete(_) | EditOp::Insert(_) | EditOp::Relabel { .. } => 1,
+                EditOp::Match { .. } => 0,
+            })
+            .sum();
+        assert_eq!(cost, tree_1.tree_edit_distance(&tree_2));
+    }
+
+    #[test]
+    fn test_distance_with_cost_closures() {
+        // labels need not be strings: here they are integers and relabeling
+        // costs the absolute difference between them
+        let tree_1_root_node = TreeNode::new(1i64);
+        let tree_2_root_node = TreeNode::new(4i64);
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        let distance = tree_1.weighted_tree_edit_distance_with(
+            &tree_2,
+            |_| 10,
+            |_| 10,
+            |a: &i64, b: &i64| a.abs_diff(*b),
+        );
+
+        // relabeling (cost 3) is cheaper than delete + insert (cost 20)
+        assert_eq!(3, distance);
+    }
+
+    #[test]
+    fn test_constrained_distance_with_trees() {
+        let tree_1_root_node = tree!("A", [tree!("B"), tree!("C")]);
+        let tree_2_root_node = tree!("A", [tree!("B"), tree!("D")]);
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        // relabel C into D, match everything else: here the constraint costs
+        // nothing and the answer agrees with the general distance
+        assert_eq!(1, tree_1.constrained_tree_edit_distance(&tree_2, 1, 1, 1));
+        assert_eq!(
+            tree_1.tree_edit_distance(&tree_2),
+            tree_1.constrained_tree_edit_distance(&tree_2, 1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_constrained_self_distance_is_zero() {
+        let root_node = tree!(
+            "A",
+            [tree!("B"), tree!("C", [tree!("E"), tree!("F")]), tree!("D")]
+        );
+        let tree = Tree::new(&root_node);
+
+        assert_eq!(0, tree.constrained_tree_edit_distance(&tree, 1, 1, 1));
+    }
+
+    #[test]
+    fn test_constrained_is_at_least_general() {
+        let tree_1_root_node = tree!("A", [tree!("B"), tree!("C"), tree!("D", [tree!("E")])]);
+        let tree_2_root_node = tree!("X", [tree!("C"), tree!("Y", [tree!("Z")])]);
+
+        let tree_1 = Tree::new(&tree_1_root_node);
+        let tree_2 = Tree::new(&tree_2_root_node);
+
+        // the structure-preserving constraint can only make the mapping costlier
+        assert!(
+            tree_1.constrained_tree_edit_distance(&tree_2, 1, 1, 1)
+                >= tree_1.tree_edit_distance(&tree_2)
+        );
+    }
+
+    #[test]
+    fn test_bracket_round_trip() {
+        let input = "A(B,C(E,F),D)";
+        let node = TreeNode::from_bracket(input).unwrap();
+
+        assert_eq!("A", node.label);
+        assert_eq!(3, node.children.len());
+        assert_eq!("C", node.children[1].label);
+        assert_eq!(2, node.children[1].children.len());
+
+        // serializing the parsed tree reproduces the input verbatim
+        assert_eq!(input, node.to_bracket());
+    }
+
+    #[test]
+    fn test_bracket_escaping_round_trips() {
+        // the label is literally `a(b,c`, with the structural characters escaped
+        let node = TreeNode::from_bracket("a\\(b\\,c").unwrap();
+        assert_eq!("a(b,c", node.label);
+        assert!(node.children.is_empty());
+        assert_eq!("a\\(b\\,c", node.to_bracket());
+    }
+
+    #[test]
+    fn test_bracket_reports_error_offset() {
+        let err = TreeNode::from_bracket("A(B,C").unwrap_err();
+        assert_eq!(ParseErrorKind::UnexpectedEnd, err.kind);
+        assert_eq!(5, err.offset);
+
+        let err = TreeNode::from_bracket("A)").unwrap_err();
+        assert_eq!(ParseErrorKind::TrailingInput, err.kind);
+        assert_eq!(1, err.offset);
+    }
+}